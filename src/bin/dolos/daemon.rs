@@ -39,16 +39,37 @@ pub async fn run(
 
     let applydb = ApplyDB::open(applydb_path).map_err(Error::storage)?;
 
+    // secondary address -> set<TxoRef> index; the spawned task backfills it from
+    // the chain store and then keeps it in step with the utxo set by following the WAL
+    let index_db = sled::open(applydb_path.join("address_index")).map_err(Error::storage)?;
+    let address_index = dolos::serve::grpc::address_index::AddressIndex::open(&index_db)?;
+
+    let metrics = dolos::serve::metrics::Metrics::new();
+
+    tokio::spawn(dolos::serve::grpc::address_index::run(
+        address_index.clone(),
+        rolldb.clone(),
+        applydb.clone(),
+        metrics.clone(),
+    ));
+
     // channel that connects output from sync pipeline to gRPC server
-    let (to_serve, from_sync) = gasket::messaging::tokio::broadcast_channel(100);
+    let (to_serve, _from_sync) = gasket::messaging::tokio::broadcast_channel(100);
 
     let rolldb_copy = rolldb.clone();
+    let applydb_copy = applydb.clone();
+
+    if let Some(metrics_config) = config.serve.metrics {
+        tokio::spawn(dolos::serve::metrics::serve(metrics_config, metrics.clone()));
+    }
 
     if let Some(grpc_config) = config.serve.grpc {
         let server = tokio::spawn(dolos::serve::grpc::serve(
             grpc_config,
             rolldb_copy,
-            from_sync.try_into().unwrap(),
+            applydb_copy,
+            address_index,
+            metrics.clone(),
         ));
 
         dolos::sync::pipeline(&config.upstream, rolldb, applydb, to_serve, policy)
@@ -0,0 +1,284 @@
+use futures_util::StreamExt;
+use pallas::{
+    crypto::hash::Hash,
+    ledger::traverse::{Era, MultiEraBlock, MultiEraOutput},
+    storage::{
+        applydb::ApplyDB,
+        rolldb::{
+            chain,
+            wal::{self, RollStream},
+            RollDB,
+        },
+    },
+};
+use std::sync::Arc;
+use tracing::error;
+
+use crate::prelude::*;
+use crate::serve::metrics::Metrics;
+
+/// A `TxoRef` as stored by the index: the producing transaction hash and the
+/// output index within it.
+pub type TxoRef = (Hash<32>, u64);
+
+/// Secondary index mapping an output address to the set of `TxoRef`s currently
+/// held at it. ApplyDB itself is keyed by `(tx_hash, output_index)` and lives in
+/// the upstream `pallas` dependency, which this crate does not fork; instead we
+/// maintain this companion column here and keep it in step with ApplyDB via the
+/// WAL apply/undo path (see [`run`]), inserting on produced outputs and deleting
+/// on spent inputs so address queries stay O(matches).
+///
+/// Consistency model: the index is eventually-consistent with ApplyDB rather
+/// than updated in the same transaction (ApplyDB being upstream, we do not hook
+/// its write path). When [`run`] starts it backfills from the immutable `chain::Store`
+/// so UTxOs produced before the current volatile WAL window are indexed, and a
+/// persisted cursor lets restarts resume instead of replaying from origin; the
+/// volatile tail is then followed from the WAL.
+#[derive(Clone)]
+pub struct AddressIndex {
+    /// `len(address) as u16 BE || address || tx_hash || index_be -> ()`, scanned
+    /// by the length-delimited address prefix so a shorter stored address can
+    /// never be a byte-prefix of a longer query address.
+    by_address: sled::Tree,
+    /// `tx_hash || index_be -> address`, used to recover the address of a spent
+    /// input so the matching `by_address` entry can be removed.
+    by_ref: sled::Tree,
+    /// single-entry `() -> slot_be` cursor of the highest slot indexed so far.
+    cursor: sled::Tree,
+}
+
+fn ref_key(hash: &Hash<32>, index: u64) -> Vec<u8> {
+    let mut key = Vec::with_capacity(40);
+    key.extend_from_slice(hash.as_ref());
+    key.extend_from_slice(&index.to_be_bytes());
+    key
+}
+
+/// Length-delimited prefix of an address, shared by `addr_key` and `get` so the
+/// prefix scan is exact for variable-length Cardano addresses.
+fn addr_prefix(address: &[u8]) -> Vec<u8> {
+    let mut prefix = Vec::with_capacity(2 + address.len());
+    prefix.extend_from_slice(&(address.len() as u16).to_be_bytes());
+    prefix.extend_from_slice(address);
+    prefix
+}
+
+fn addr_key(address: &[u8], hash: &Hash<32>, index: u64) -> Vec<u8> {
+    let mut key = addr_prefix(address);
+    key.extend_from_slice(&ref_key(hash, index));
+    key
+}
+
+impl AddressIndex {
+    pub fn open(db: &sled::Db) -> Result<Self, Error> {
+        Ok(Self {
+            by_address: db.open_tree(b"address_utxo").map_err(Error::storage)?,
+            by_ref: db.open_tree(b"address_utxo_ref").map_err(Error::storage)?,
+            cursor: db.open_tree(b"address_utxo_cursor").map_err(Error::storage)?,
+        })
+    }
+
+    fn insert(&self, address: &[u8], hash: &Hash<32>, index: u64) -> Result<(), Error> {
+        self.by_address
+            .insert(addr_key(address, hash, index), &[])
+            .map_err(Error::storage)?;
+        self.by_ref
+            .insert(ref_key(hash, index), address)
+            .map_err(Error::storage)?;
+        Ok(())
+    }
+
+    fn remove(&self, hash: &Hash<32>, index: u64) -> Result<(), Error> {
+        if let Some(address) = self.by_ref.remove(ref_key(hash, index)).map_err(Error::storage)? {
+            self.by_address
+                .remove(addr_key(&address, hash, index))
+                .map_err(Error::storage)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the `TxoRef`s currently indexed at `address`.
+    pub fn get(&self, address: &[u8]) -> Result<Vec<TxoRef>, Error> {
+        let prefix = addr_prefix(address);
+        let skip = prefix.len();
+
+        self.by_address
+            .scan_prefix(&prefix)
+            .keys()
+            .map(|key| {
+                let key = key.map_err(Error::storage)?;
+                let tail = &key[skip..];
+                let hash = Hash::<32>::from(&tail[..32]);
+                let index = u64::from_be_bytes(tail[32..40].try_into().unwrap());
+                Ok((hash, index))
+            })
+            .collect()
+    }
+
+    fn cursor(&self) -> Result<Option<u64>, Error> {
+        let raw = self.cursor.get([]).map_err(Error::storage)?;
+        Ok(raw.map(|v| u64::from_be_bytes(v.as_ref().try_into().unwrap())))
+    }
+
+    fn set_cursor(&self, slot: u64) -> Result<(), Error> {
+        self.cursor
+            .insert([], &slot.to_be_bytes())
+            .map_err(Error::storage)?;
+        Ok(())
+    }
+
+    /// Updates the index for a block being applied: produced outputs are
+    /// inserted and spent inputs removed, mirroring ApplyDB's own transition.
+    pub fn apply_block(&self, cbor: &[u8]) -> Result<(), Error> {
+        let block = MultiEraBlock::decode(cbor).map_err(Error::message)?;
+
+        for tx in block.txs() {
+            for input in tx.consumes() {
+                self.remove(input.hash(), input.index())?;
+            }
+
+            let hash = tx.hash();
+            for (index, output) in tx.produces() {
+                self.index_output(&hash, index as u64, &output)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reverses [`apply_block`] for a rollback: produced outputs are removed and
+    /// spent inputs reinstated. A `MultiEraInput` only carries a reference, so
+    /// the reinstated output's address is resolved from ApplyDB, which has
+    /// already restored the spent UTxO at undo time.
+    pub fn undo_block(&self, cbor: &[u8], applydb: &ApplyDB) -> Result<(), Error> {
+        let block = MultiEraBlock::decode(cbor).map_err(Error::message)?;
+
+        for tx in block.txs() {
+            let hash = tx.hash();
+            for (index, _) in tx.produces() {
+                self.remove(&hash, index as u64)?;
+            }
+
+            for input in tx.consumes() {
+                let (in_hash, in_index) = (*input.hash(), input.index());
+
+                if let Some(era_cbor) = applydb
+                    .get_utxo(in_hash, in_index)
+                    .map_err(Error::storage)?
+                {
+                    let era = Era::try_from(era_cbor.0).map_err(Error::message)?;
+                    let output =
+                        MultiEraOutput::decode(era, &era_cbor.1).map_err(Error::message)?;
+                    self.index_output(&in_hash, in_index, &output)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn index_output(
+        &self,
+        hash: &Hash<32>,
+        index: u64,
+        output: &MultiEraOutput,
+    ) -> Result<(), Error> {
+        if let Ok(address) = output.address() {
+            self.insert(&address.to_vec(), hash, index)?;
+        }
+
+        Ok(())
+    }
+
+    /// Backfills the index from the immutable chain store, covering everything
+    /// up to the start of the volatile WAL window, and advances the cursor.
+    /// Blocks already indexed (before the persisted cursor) are skipped.
+    fn backfill(&self, chain: &chain::Store) -> Result<(), Error> {
+        const PAGE_LEN: usize = 100;
+
+        let start = self.cursor()?.map(|s| s + 1).unwrap_or_default();
+        let mut from = start;
+
+        loop {
+            let page: Vec<_> = chain
+                .read_chain_page(from, PAGE_LEN + 1)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(Error::storage)?;
+
+            if page.is_empty() {
+                break;
+            }
+
+            // reserve the inclusive extra element as the next `from`
+            let (emit, next) = if page.len() > PAGE_LEN {
+                (&page[..PAGE_LEN], Some(page[PAGE_LEN].0))
+            } else {
+                (&page[..], None)
+            };
+
+            for (slot, hash) in emit {
+                if *slot < start {
+                    continue;
+                }
+
+                if let Some(raw) = chain.get_block(*hash).map_err(Error::storage)? {
+                    self.apply_block(&raw)?;
+                }
+
+                self.set_cursor(*slot)?;
+            }
+
+            match next {
+                Some(f) => from = f,
+                None => break,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Keeps `index` in step with the utxo set: backfills from the immutable chain
+/// store on start, then follows the WAL's apply/undo logs — the apply/undo path
+/// available to this crate (ApplyDB living upstream and not being forked).
+///
+/// As the single global consumer of the WAL it is also where the cumulative
+/// Apply/Undo counters and the tip-slot gauge are updated, so they measure WAL
+/// activity once regardless of how many `follow_tip` subscribers are attached.
+pub async fn run(index: AddressIndex, rolldb: RollDB, applydb: ApplyDB, metrics: Arc<Metrics>) {
+    let (wal, chain) = rolldb.split();
+
+    if let Err(e) = index.backfill(&chain) {
+        error!("address index backfill error: {e}");
+    }
+
+    let mut stream = RollStream::stream_wal(wal, None);
+
+    while let Some(log) = stream.next().await {
+        let result = match log {
+            Ok(wal::Log::Apply(slot, _, block)) => {
+                metrics.apply_logs.inc();
+                metrics.tip_slot.set(slot as i64);
+                index.apply_block(&block).and_then(|_| index.set_cursor(slot))
+            }
+            Ok(wal::Log::Undo(slot, _, block)) => {
+                metrics.undo_logs.inc();
+                index.undo_block(&block, &applydb)
+            }
+            Ok(wal::Log::Mark(slot, _, _)) => {
+                // a rollback ends on its mark, which is the new tip
+                metrics.tip_slot.set(slot as i64);
+                Ok(())
+            }
+            Ok(_) => Ok(()),
+            Err(e) => {
+                error!("address index wal error: {e}");
+                continue;
+            }
+        };
+
+        if let Err(e) = result {
+            error!("address index error: {e}");
+        }
+    }
+}
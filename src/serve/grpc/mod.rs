@@ -0,0 +1,87 @@
+use std::sync::Arc;
+
+use pallas::storage::{applydb::ApplyDB, rolldb::RollDB};
+use serde::Deserialize;
+use tonic::{codec::CompressionEncoding, transport::Server};
+use utxorpc_spec::utxorpc::v1alpha as u5c;
+
+use crate::prelude::*;
+use crate::serve::metrics::Metrics;
+
+pub mod address_index;
+mod query;
+mod sync;
+mod watch;
+
+use address_index::AddressIndex;
+use query::QueryServiceImpl;
+use sync::ChainSyncServiceImpl;
+use watch::WatchServiceImpl;
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Encoding {
+    Gzip,
+    Zstd,
+}
+
+impl From<Encoding> for CompressionEncoding {
+    fn from(value: Encoding) -> Self {
+        match value {
+            Encoding::Gzip => CompressionEncoding::Gzip,
+            Encoding::Zstd => CompressionEncoding::Zstd,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Config {
+    pub listen_address: String,
+
+    /// Content encodings the server accepts from and offers to clients. Block
+    /// payloads mapped through `raw_to_anychain` are large, so negotiated
+    /// compression noticeably cuts bandwidth for `dump_history` and tip
+    /// streaming; per-call encoding is chosen from the client's
+    /// `grpc-accept-encoding`.
+    #[serde(default)]
+    pub compression: Vec<Encoding>,
+}
+
+pub async fn serve(
+    config: Config,
+    rolldb: RollDB,
+    applydb: ApplyDB,
+    address_index: AddressIndex,
+    metrics: Arc<Metrics>,
+) -> Result<(), Error> {
+    let addr = config.listen_address.parse().map_err(Error::config)?;
+
+    let (wal, chain) = rolldb.split();
+
+    let sync_service = ChainSyncServiceImpl::new(wal.clone(), chain, metrics);
+    let mut sync_service =
+        u5c::sync::chain_sync_service_server::ChainSyncServiceServer::new(sync_service);
+
+    for encoding in config.compression.iter().copied() {
+        let encoding = CompressionEncoding::from(encoding);
+        sync_service = sync_service
+            .send_compressed(encoding)
+            .accept_compressed(encoding);
+    }
+
+    let query_service = QueryServiceImpl::new(applydb, address_index);
+    let query_service = u5c::query::query_service_server::QueryServiceServer::new(query_service);
+
+    let watch_service = WatchServiceImpl::new(wal);
+    let watch_service = u5c::watch::watch_service_server::WatchServiceServer::new(watch_service);
+
+    Server::builder()
+        .add_service(sync_service)
+        .add_service(query_service)
+        .add_service(watch_service)
+        .serve(addr)
+        .await
+        .map_err(Error::server)?;
+
+    Ok(())
+}
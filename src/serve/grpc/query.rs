@@ -0,0 +1,153 @@
+use pallas::{
+    crypto::hash::Hash,
+    ledger::traverse::{Era, MultiEraOutput},
+    storage::applydb::ApplyDB,
+};
+use tonic::{Request, Response, Status};
+use utxorpc_spec::utxorpc::v1alpha::{self as u5c, query::any_utxo_data};
+
+use super::address_index::AddressIndex;
+
+fn bytes_to_hash(raw: &[u8]) -> Result<Hash<32>, Status> {
+    let array: [u8; 32] = raw
+        .try_into()
+        .map_err(|_| Status::invalid_argument("tx hash must be 32 bytes"))?;
+
+    Ok(Hash::<32>::new(array))
+}
+
+fn raw_to_anyutxo(
+    txo: &u5c::query::TxoRef,
+    era: u16,
+    cbor: &[u8],
+) -> Result<u5c::query::AnyUtxoData, Status> {
+    let era = Era::try_from(era).map_err(|_| Status::internal("unknown output era"))?;
+
+    let output =
+        MultiEraOutput::decode(era, cbor).map_err(|_| Status::internal("can't decode utxo"))?;
+
+    let parsed = pallas::interop::utxorpc::map_tx_output(&output);
+
+    Ok(u5c::query::AnyUtxoData {
+        txo_ref: Some(txo.clone()),
+        native_bytes: cbor.to_vec().into(),
+        parsed_state: any_utxo_data::ParsedState::Cardano(parsed).into(),
+    })
+}
+
+pub struct QueryServiceImpl {
+    applydb: ApplyDB,
+    address_index: AddressIndex,
+}
+
+impl QueryServiceImpl {
+    pub fn new(applydb: ApplyDB, address_index: AddressIndex) -> Self {
+        Self {
+            applydb,
+            address_index,
+        }
+    }
+
+    fn load_utxo(&self, txo: &u5c::query::TxoRef) -> Result<Option<u5c::query::AnyUtxoData>, Status> {
+        let hash = bytes_to_hash(&txo.hash)?;
+
+        let maybe = self
+            .applydb
+            .get_utxo(hash, txo.index as u64)
+            .map_err(|_| Status::internal("can't query utxo"))?;
+
+        maybe
+            .map(|era_cbor| raw_to_anyutxo(txo, era_cbor.0, &era_cbor.1))
+            .transpose()
+    }
+}
+
+#[async_trait::async_trait]
+impl u5c::query::query_service_server::QueryService for QueryServiceImpl {
+    async fn read_utxos(
+        &self,
+        request: Request<u5c::query::ReadUtxosRequest>,
+    ) -> Result<Response<u5c::query::ReadUtxosResponse>, Status> {
+        let message = request.into_inner();
+
+        let items = message
+            .keys
+            .iter()
+            .map(|txo| self.load_utxo(txo))
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .flatten()
+            .collect();
+
+        let response = u5c::query::ReadUtxosResponse {
+            items,
+            ..Default::default()
+        };
+
+        Ok(Response::new(response))
+    }
+
+    async fn search_utxos(
+        &self,
+        request: Request<u5c::query::SearchUtxosRequest>,
+    ) -> Result<Response<u5c::query::SearchUtxosResponse>, Status> {
+        let message = request.into_inner();
+
+        let predicate = message
+            .predicate
+            .and_then(|p| p.r#match)
+            .ok_or_else(|| Status::invalid_argument("missing utxo predicate"))?;
+
+        let pattern = match predicate.utxo_pattern {
+            Some(u5c::cardano::any_chain_utxo_pattern::UtxoPattern::Cardano(p)) => p,
+            _ => return Err(Status::invalid_argument("unsupported utxo predicate")),
+        };
+
+        let address = pattern
+            .address
+            .ok_or_else(|| Status::invalid_argument("only address search is supported"))?;
+
+        // the index is keyed by full address; payment-credential search is not
+        // supported yet
+        if !address.payment_part.is_empty() {
+            return Err(Status::unimplemented(
+                "payment-credential search is not supported; the address index is keyed by full address",
+            ));
+        }
+
+        if address.exact_address.is_empty() {
+            return Err(Status::invalid_argument(
+                "address search requires a non-empty exact_address",
+            ));
+        }
+
+        // resolve the address against the secondary index instead of scanning
+        // the whole utxo set; the index is keyed by the raw address bytes.
+        let refs = self
+            .address_index
+            .get(&address.exact_address)
+            .map_err(|_| Status::internal("can't query address index"))?;
+
+        let items = refs
+            .into_iter()
+            .map(|(hash, index)| {
+                let txo = u5c::query::TxoRef {
+                    hash: hash.to_vec().into(),
+                    index: index as u32,
+                };
+
+                self.load_utxo(&txo)
+            })
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .flatten()
+            .collect();
+
+        let response = u5c::query::SearchUtxosResponse {
+            items,
+            ..Default::default()
+        };
+
+        Ok(Response::new(response))
+    }
+}
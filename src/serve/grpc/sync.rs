@@ -6,12 +6,17 @@ use pallas::{
         wal::{self, RollStream},
     },
 };
+use std::collections::VecDeque;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 use tokio_stream::StreamExt;
 use tonic::{Request, Response, Status};
 use tracing::error;
 use utxorpc_spec::utxorpc::v1alpha::{self as u5c, sync::BlockRef};
 
+use crate::serve::metrics::{Metrics, StreamGuard};
+
 fn bytes_to_hash(raw: &[u8]) -> Hash<32> {
     let array: [u8; 32] = raw.try_into().unwrap();
     Hash::<32>::new(array)
@@ -55,11 +60,125 @@ fn roll_to_tip_response(log: wal::Log) -> u5c::sync::FollowTipResponse {
 pub struct ChainSyncServiceImpl {
     wal: wal::Store,
     chain: chain::Store,
+    metrics: Arc<Metrics>,
 }
 
 impl ChainSyncServiceImpl {
-    pub fn new(wal: wal::Store, chain: chain::Store) -> Self {
-        Self { wal, chain }
+    pub fn new(wal: wal::Store, chain: chain::Store, metrics: Arc<Metrics>) -> Self {
+        Self {
+            wal,
+            chain,
+            metrics,
+        }
+    }
+
+    /// Lazily streams the blocks sitting after `after_slot` in the immutable
+    /// chain store, in chain order, as `Apply` responses. Used to replay the
+    /// history that has already been flushed out of the volatile WAL when a
+    /// follower resumes from an old intersect.
+    ///
+    /// The stream pages through the store (rather than buffering the whole
+    /// history in memory) and records the slot of the last emitted block in
+    /// `boundary`, so the WAL handoff can skip anything the replay already
+    /// covered. `read_chain_page` is inclusive of its `from`, so each page
+    /// reserves its extra element as the next `from` and does not emit it,
+    /// avoiding the duplicate at every page boundary.
+    fn chain_history_after(
+        &self,
+        after_slot: u64,
+        boundary: Arc<AtomicU64>,
+    ) -> impl Stream<Item = Result<u5c::sync::FollowTipResponse, Status>> + Send + 'static {
+        struct State {
+            chain: chain::Store,
+            after: u64,
+            from: u64,
+            done: bool,
+            pending: VecDeque<Result<u5c::sync::FollowTipResponse, Status>>,
+            boundary: Arc<AtomicU64>,
+        }
+
+        let state = State {
+            chain: self.chain.clone(),
+            after: after_slot,
+            from: after_slot,
+            done: false,
+            pending: VecDeque::new(),
+            boundary,
+        };
+
+        futures_util::stream::unfold(state, |mut st| async move {
+            const PAGE_LEN: usize = 100;
+
+            loop {
+                if let Some(item) = st.pending.pop_front() {
+                    return Some((item, st));
+                }
+
+                if st.done {
+                    return None;
+                }
+
+                let page: Result<Vec<_>, _> = st
+                    .chain
+                    .read_chain_page(st.from, PAGE_LEN + 1)
+                    .collect();
+
+                let page = match page {
+                    Ok(page) => page,
+                    Err(_) => {
+                        st.done = true;
+                        return Some((Err(Status::internal("can't query chain")), st));
+                    }
+                };
+
+                if page.is_empty() {
+                    return None;
+                }
+
+                // the extra element becomes the next (inclusive) `from` and is
+                // not emitted this round
+                let emit = if page.len() > PAGE_LEN {
+                    st.from = page[PAGE_LEN].0;
+                    &page[..PAGE_LEN]
+                } else {
+                    st.done = true;
+                    &page[..]
+                };
+
+                for (slot, hash) in emit {
+                    // skip the intersect point itself (inclusive `from`)
+                    if *slot <= st.after {
+                        continue;
+                    }
+
+                    match st.chain.get_block(*hash) {
+                        Ok(Some(raw)) => {
+                            st.boundary.store(*slot, Ordering::SeqCst);
+                            st.pending.push_back(Ok(u5c::sync::FollowTipResponse {
+                                action: u5c::sync::follow_tip_response::Action::Apply(
+                                    raw_to_anychain(&raw),
+                                )
+                                .into(),
+                            }));
+                        }
+                        Ok(None) => {}
+                        Err(_) => st
+                            .pending
+                            .push_back(Err(Status::internal("can't query chain"))),
+                    }
+                }
+            }
+        })
+    }
+}
+
+/// Slot carried by a WAL log, if any.
+fn log_slot(log: &wal::Log) -> Option<u64> {
+    match log {
+        wal::Log::Apply(slot, _, _)
+        | wal::Log::Undo(slot, _, _)
+        | wal::Log::Mark(slot, _, _) => Some(*slot),
+        wal::Log::Origin => None,
     }
 }
 
@@ -81,8 +200,16 @@ impl u5c::sync::chain_sync_service_server::ChainSyncService for ChainSyncService
             .map(|hash| self.chain.get_block(hash))
             .collect();
 
+        let blocks = blocks.map_err(|_err| Status::internal("can't query block"))?;
+
+        for block in blocks.iter() {
+            match block {
+                Some(_) => self.metrics.fetch_block_hits.inc(),
+                None => self.metrics.fetch_block_misses.inc(),
+            }
+        }
+
         let out: Vec<_> = blocks
-            .map_err(|_err| Status::internal("can't query block"))?
             .iter()
             .flatten()
             .map(|b| raw_to_anychain(b))
@@ -97,6 +224,8 @@ impl u5c::sync::chain_sync_service_server::ChainSyncService for ChainSyncService
         &self,
         request: Request<u5c::sync::DumpHistoryRequest>,
     ) -> Result<Response<u5c::sync::DumpHistoryResponse>, Status> {
+        let started = std::time::Instant::now();
+
         let msg = request.into_inner();
         let from = msg.start_token.map(|r| r.index).unwrap_or_default();
         let len = msg.max_items as usize + 1;
@@ -127,7 +256,12 @@ impl u5c::sync::chain_sync_service_server::ChainSyncService for ChainSyncService
             .collect::<Result<Vec<_>, _>>()?
             .into_iter()
             .map(|raw| raw_to_anychain(&raw))
-            .collect();
+            .collect::<Vec<_>>();
+
+        self.metrics.dump_history_items.observe(blocks.len() as f64);
+        self.metrics
+            .dump_history_latency
+            .observe(started.elapsed().as_secs_f64());
 
         let response = u5c::sync::DumpHistoryResponse {
             block: blocks,
@@ -151,11 +285,17 @@ impl u5c::sync::chain_sync_service_server::ChainSyncService for ChainSyncService
 
         // if no intersect provided, stream WAL from start
         if intersects.is_empty() {
-            let stream = RollStream::stream_wal(self.wal.clone(), None).map(|x| match x {
-                Ok(log) => Ok(roll_to_tip_response(log)),
-                Err(e) => {
-                    error!("rollstream error: {e}");
-                    Err(Status::internal("rollstream error"))
+            let guard = StreamGuard::new(self.metrics.clone());
+
+            let stream = RollStream::stream_wal(self.wal.clone(), None).map(move |x| {
+                // keep the active-stream guard alive for the lifetime of the stream
+                let _guard = &guard;
+                match x {
+                    Ok(log) => Ok(roll_to_tip_response(log)),
+                    Err(e) => {
+                        error!("rollstream error: {e}");
+                        Err(Status::internal("rollstream error"))
+                    }
                 }
             });
 
@@ -163,19 +303,24 @@ impl u5c::sync::chain_sync_service_server::ChainSyncService for ChainSyncService
         }
 
         // else try intersect with the provided intersects
-        for intersect in intersects {
+        for intersect in intersects.iter().copied() {
             let maybe_wal_seq = self
                 .wal
                 .find_wal_seq(&[intersect])
                 .map_err(|_| Status::internal("kvtable error"))?;
 
             if let Some(wal_seq) = maybe_wal_seq {
+                let guard = StreamGuard::new(self.metrics.clone());
+
                 let stream =
-                    RollStream::stream_wal(self.wal.clone(), Some(wal_seq)).map(|x| match x {
-                        Ok(log) => Ok(roll_to_tip_response(log)),
-                        Err(e) => {
-                            error!("rollstream error: {e}");
-                            Err(Status::internal("rollstream error"))
+                    RollStream::stream_wal(self.wal.clone(), Some(wal_seq)).map(move |x| {
+                        let _guard = &guard;
+                        match x {
+                            Ok(log) => Ok(roll_to_tip_response(log)),
+                            Err(e) => {
+                                error!("rollstream error: {e}");
+                                Err(Status::internal("rollstream error"))
+                            }
                         }
                     });
 
@@ -183,9 +328,54 @@ impl u5c::sync::chain_sync_service_server::ChainSyncService for ChainSyncService
             }
         }
 
-        // error if we found no intersect
-        Err(Status::not_found(
-            "no intersect found in mutable part of chain",
-        ))
+        // none of the intersects are in the volatile WAL anymore; fall back to
+        // the immutable chain store so long-lived followers can resume from
+        // history instead of being forced into a full re-sync.
+        for &(slot, hash) in intersects.iter() {
+            let found = self
+                .chain
+                .get_block(hash)
+                .map_err(|_| Status::internal("can't query chain"))?
+                .is_some();
+
+            if !found {
+                continue;
+            }
+
+            // replay the flushed blocks after the intersect as Apply actions,
+            // tracking the slot of the last replayed block...
+            let boundary = Arc::new(AtomicU64::new(slot));
+            let historical = self.chain_history_after(slot, boundary.clone());
+
+            // ...then hand off to the WAL, skipping any log at or before the
+            // replayed boundary so the overlapping region between chain and WAL
+            // is streamed exactly once (neither gaps nor duplicates).
+            let guard = StreamGuard::new(self.metrics.clone());
+
+            let live = RollStream::stream_wal(self.wal.clone(), None).filter_map(move |x| {
+                let _guard = &guard;
+                match x {
+                    Ok(log) => {
+                        if log_slot(&log)
+                            .map(|s| s <= boundary.load(Ordering::SeqCst))
+                            .unwrap_or(false)
+                        {
+                            None
+                        } else {
+                            Some(Ok(roll_to_tip_response(log)))
+                        }
+                    }
+                    Err(e) => {
+                        error!("rollstream error: {e}");
+                        Some(Err(Status::internal("rollstream error")))
+                    }
+                }
+            });
+
+            return Ok(Response::new(Box::pin(historical.chain(live))));
+        }
+
+        // error if we found no intersect anywhere
+        Err(Status::not_found("no intersect found in chain"))
     }
 }
@@ -0,0 +1,155 @@
+use std::{convert::Infallible, net::SocketAddr, sync::Arc};
+
+use hyper::{
+    service::{make_service_fn, service_fn},
+    Body, Request, Response, Server,
+};
+use prometheus::{
+    Encoder, Histogram, HistogramOpts, IntCounter, IntGauge, Opts, Registry, TextEncoder,
+};
+use serde::Deserialize;
+
+use crate::prelude::*;
+
+#[derive(Debug, Deserialize)]
+pub struct Config {
+    pub listen_address: String,
+}
+
+/// Collection of Prometheus instruments shared between the serving layer and
+/// the HTTP exporter. It is shared as an `Arc`; cloning that `Arc` hands out
+/// another handle onto the same registry.
+pub struct Metrics {
+    registry: Registry,
+    pub tip_slot: IntGauge,
+    pub apply_logs: IntCounter,
+    pub undo_logs: IntCounter,
+    pub follow_tip_active: IntGauge,
+    pub dump_history_items: Histogram,
+    pub dump_history_latency: Histogram,
+    pub fetch_block_hits: IntCounter,
+    pub fetch_block_misses: IntCounter,
+}
+
+impl Metrics {
+    pub fn new() -> Arc<Self> {
+        let registry = Registry::new();
+
+        let tip_slot =
+            IntGauge::with_opts(Opts::new("dolos_tip_slot", "slot of the current chain tip"))
+                .unwrap();
+        let apply_logs = IntCounter::with_opts(Opts::new(
+            "dolos_wal_apply_total",
+            "cumulative Apply logs seen on the WAL",
+        ))
+        .unwrap();
+        let undo_logs = IntCounter::with_opts(Opts::new(
+            "dolos_wal_undo_total",
+            "cumulative Undo logs seen on the WAL",
+        ))
+        .unwrap();
+        let follow_tip_active = IntGauge::with_opts(Opts::new(
+            "dolos_follow_tip_active",
+            "number of active follow_tip streams",
+        ))
+        .unwrap();
+        let dump_history_items = Histogram::with_opts(HistogramOpts::new(
+            "dolos_dump_history_items",
+            "page size returned by dump_history",
+        ))
+        .unwrap();
+        let dump_history_latency = Histogram::with_opts(HistogramOpts::new(
+            "dolos_dump_history_latency_seconds",
+            "wall-clock latency of dump_history calls",
+        ))
+        .unwrap();
+        let fetch_block_hits = IntCounter::with_opts(Opts::new(
+            "dolos_fetch_block_hits_total",
+            "fetch_block lookups served from the chain store",
+        ))
+        .unwrap();
+        let fetch_block_misses = IntCounter::with_opts(Opts::new(
+            "dolos_fetch_block_misses_total",
+            "fetch_block lookups missing from the chain store",
+        ))
+        .unwrap();
+
+        registry.register(Box::new(tip_slot.clone())).unwrap();
+        registry.register(Box::new(apply_logs.clone())).unwrap();
+        registry.register(Box::new(undo_logs.clone())).unwrap();
+        registry
+            .register(Box::new(follow_tip_active.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(dump_history_items.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(dump_history_latency.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(fetch_block_hits.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(fetch_block_misses.clone()))
+            .unwrap();
+
+        Arc::new(Self {
+            registry,
+            tip_slot,
+            apply_logs,
+            undo_logs,
+            follow_tip_active,
+            dump_history_items,
+            dump_history_latency,
+            fetch_block_hits,
+            fetch_block_misses,
+        })
+    }
+
+    fn gather(&self) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        let encoder = TextEncoder::new();
+        let families = self.registry.gather();
+        encoder.encode(&families, &mut buffer).unwrap();
+        buffer
+    }
+}
+
+/// RAII guard that tracks the number of live `follow_tip` streams: it bumps the
+/// gauge on construction and releases it when the stream is dropped.
+pub struct StreamGuard(Arc<Metrics>);
+
+impl StreamGuard {
+    pub fn new(metrics: Arc<Metrics>) -> Self {
+        metrics.follow_tip_active.inc();
+        Self(metrics)
+    }
+}
+
+impl Drop for StreamGuard {
+    fn drop(&mut self) {
+        self.0.follow_tip_active.dec();
+    }
+}
+
+async fn handle(metrics: Arc<Metrics>) -> Result<Response<Body>, Infallible> {
+    Ok(Response::new(Body::from(metrics.gather())))
+}
+
+pub async fn serve(config: Config, metrics: Arc<Metrics>) -> Result<(), Error> {
+    let addr: SocketAddr = config.listen_address.parse().map_err(Error::config)?;
+
+    let make_service = make_service_fn(move |_| {
+        let metrics = metrics.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |_req: Request<Body>| handle(metrics.clone())))
+        }
+    });
+
+    Server::bind(&addr)
+        .serve(make_service)
+        .await
+        .map_err(Error::server)?;
+
+    Ok(())
+}